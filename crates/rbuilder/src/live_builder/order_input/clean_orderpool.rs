@@ -2,82 +2,527 @@ use super::OrderInputConfig;
 use crate::{
     live_builder::order_input::orderpool::OrderPool,
     provider::StateProviderFactory,
-    telemetry::{set_current_block, set_ordepool_count},
+    telemetry::{
+        record_block_gap, record_head_update_lag, record_reorg, set_current_block,
+        set_ordepool_count,
+    },
 };
 use ethers::{
     middleware::Middleware,
     providers::{Ipc, Provider},
+    types::{Block, TxHash, H256, U256, U64},
 };
-use futures::StreamExt;
+use futures::{stream::unfold, Stream, StreamExt};
 use std::{
-    pin::pin,
+    collections::VecDeque,
+    path::PathBuf,
+    pin::{pin, Pin},
     sync::{Arc, Mutex},
-    time::Instant,
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::{broadcast, mpsc},
+    task::JoinHandle,
 };
-use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
+
+/// Default interval used to poll for new blocks when the configured node does not support
+/// `eth_subscribe`/`newHeads`.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How many recent `(number, hash)` pairs [`BlockSequencer`] remembers. Only needs to be big
+/// enough to resolve reorgs that are shallow relative to how quickly we process new heads.
+const SEQUENCER_LRU_CAPACITY: usize = 64;
+
+/// A new block header as delivered by either a subscription or a polling [`BlockSource`].
+type BlockHeader = Block<TxHash>;
+
+/// Where new blocks come from: a push subscription, or a polling fallback when the node
+/// doesn't support one.
+enum BlockSource {
+    /// Node supports `eth_subscribe("newHeads")`.
+    Subscription,
+    /// Node doesn't support subscriptions; poll `eth_blockNumber` at this interval instead.
+    Polling { poll_interval: Duration },
+}
+
+impl BlockSource {
+    /// Probes `provider` for subscription support and picks the appropriate variant.
+    async fn detect(provider: &Provider<Ipc>) -> Self {
+        match provider.subscribe_blocks().await {
+            Ok(sub) => {
+                sub.unsubscribe().await.unwrap_or_default();
+                BlockSource::Subscription
+            }
+            Err(err) => {
+                warn!(
+                    "Node does not support block subscriptions ({:?}), falling back to polling",
+                    err
+                );
+                BlockSource::Polling {
+                    poll_interval: DEFAULT_POLL_INTERVAL,
+                }
+            }
+        }
+    }
+
+    /// Turns this source into a unified stream of block headers.
+    fn into_stream(
+        self,
+        provider: Provider<Ipc>,
+    ) -> Pin<Box<dyn Stream<Item = BlockHeader> + Send>> {
+        match self {
+            BlockSource::Subscription => Box::pin(
+                futures::stream::once(async move { provider.subscribe_blocks().await })
+                    .filter_map(|res| async move {
+                        match res {
+                            Ok(sub) => Some(sub),
+                            Err(err) => {
+                                error!("Failed to subscribe to a new block stream: {:?}", err);
+                                None
+                            }
+                        }
+                    })
+                    .flatten(),
+            ),
+            BlockSource::Polling { poll_interval } => poll_blocks(provider, poll_interval),
+        }
+    }
+}
+
+/// Polls `provider` for new blocks at `poll_interval`, emitting each new block number's header
+/// exactly once.
+fn poll_blocks<M: Middleware + Send + 'static>(
+    provider: M,
+    poll_interval: Duration,
+) -> Pin<Box<dyn Stream<Item = BlockHeader> + Send>> {
+    Box::pin(unfold(
+        (provider, poll_interval, None::<U64>),
+        |(provider, poll_interval, last_seen)| async move {
+            let mut ticker = tokio::time::interval(poll_interval);
+            // The first tick fires immediately; consume it up front so that every poll in the
+            // loop below, the first one included, waits a full `poll_interval`.
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let block_number = match provider.get_block_number().await {
+                    Ok(number) => number,
+                    Err(err) => {
+                        error!("Polling block source: failed to get block number: {:?}", err);
+                        continue;
+                    }
+                };
+                if last_seen == Some(block_number) {
+                    continue;
+                }
+                match provider.get_block(block_number).await {
+                    Ok(Some(block)) => {
+                        return Some((block, (provider, poll_interval, Some(block_number))));
+                    }
+                    Ok(None) => continue,
+                    Err(err) => {
+                        error!("Polling block source: failed to fetch block {}: {:?}", block_number, err);
+                        continue;
+                    }
+                }
+            }
+        },
+    ))
+}
+
+/// Connects to a single IPC endpoint and turns it into a unified block header stream, also
+/// handing back a cloned provider handle for on-demand requests (e.g. backfilling blocks).
+async fn connect_block_source(
+    ipc_path: PathBuf,
+) -> eyre::Result<(Provider<Ipc>, Pin<Box<dyn Stream<Item = BlockHeader> + Send>>)> {
+    let ipc = Ipc::connect(ipc_path).await?;
+    let provider = Provider::new(ipc);
+    let block_source = BlockSource::detect(&provider).await;
+    let handle = provider.clone();
+    Ok((handle, block_source.into_stream(provider)))
+}
+
+/// Merges several streams fastest-wins: each source is driven by its own task pushing every
+/// item it receives into a shared channel, so a stalled source can't block the others. Dropping
+/// duplicates of an already-seen item (e.g. the same block from a slower source) is left to the
+/// caller.
+fn merge_fastest_wins<T: Send + 'static>(
+    sources: Vec<Pin<Box<dyn Stream<Item = T> + Send>>>,
+) -> impl Stream<Item = T> {
+    let (tx, rx) = mpsc::channel(64);
+    for mut source in sources {
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            while let Some(item) = source.next().await {
+                if tx.send(item).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    drop(tx);
+    unfold(rx, |mut rx| async move { rx.recv().await.map(|item| (item, rx)) })
+}
+
+/// Tracks chain continuity so a dropped subscription notification (a gap) or a reorg doesn't
+/// silently leave the orderpool thinking the chain is contiguous, by comparing each incoming
+/// header's `parent_hash` against a small LRU of recently processed `(number, hash)` pairs.
+struct BlockSequencer {
+    /// Recently processed `(number, hash)` pairs, oldest first.
+    recent: VecDeque<(u64, H256)>,
+}
+
+impl BlockSequencer {
+    fn new() -> Self {
+        Self {
+            recent: VecDeque::with_capacity(SEQUENCER_LRU_CAPACITY),
+        }
+    }
+
+    fn last(&self) -> Option<(u64, H256)> {
+        self.recent.back().copied()
+    }
+
+    /// Whether `(number, hash)` has already been recorded, anywhere in the recent history (not
+    /// just at the current tip). Used to recognize a block redelivered by a slower redundant
+    /// source as a plain duplicate rather than a reorg.
+    fn contains(&self, number: u64, hash: H256) -> bool {
+        self.recent.iter().any(|&(n, h)| n == number && h == hash)
+    }
+
+    /// Records a processed block, trimming any entries this invalidates (i.e. a replayed branch
+    /// after a reorg) as well as the oldest entry once we're over capacity.
+    fn record(&mut self, number: u64, hash: H256) {
+        while matches!(self.recent.back(), Some((n, _)) if *n >= number) {
+            self.recent.pop_back();
+        }
+        self.recent.push_back((number, hash));
+        if self.recent.len() > SEQUENCER_LRU_CAPACITY {
+            self.recent.pop_front();
+        }
+    }
+}
+
+fn header_key(header: &BlockHeader) -> Option<(u64, H256)> {
+    Some((header.number?.as_u64(), header.hash?))
+}
+
+/// Fetches canonical headers for `from..=to_inclusive` (empty range if `from > to_inclusive`).
+async fn backfill<M: Middleware>(provider: &M, from: u64, to_inclusive: u64) -> Vec<BlockHeader> {
+    let mut headers = Vec::new();
+    for number in from..=to_inclusive {
+        match provider.get_block(number).await {
+            Ok(Some(block)) => headers.push(block),
+            Ok(None) => warn!("Backfill: block {} missing from canonical chain", number),
+            Err(err) => error!("Backfill: failed to fetch block {}: {:?}", number, err),
+        }
+    }
+    headers
+}
+
+/// Feeds `header` through the sequencer, returning the headers (in order) that `head_updated`
+/// should actually be called with. Returns an empty vec for a plain duplicate (already-seen
+/// block arriving from a redundant source), a single header for the contiguous case, and
+/// multiple headers when a gap or reorg needed to be backfilled.
+async fn sequence_header<M: Middleware>(
+    sequencer: &mut BlockSequencer,
+    provider: &M,
+    header: BlockHeader,
+) -> Vec<BlockHeader> {
+    let Some((number, hash)) = header_key(&header) else {
+        return vec![header];
+    };
+    let parent_hash = header.parent_hash;
+
+    let Some((last_number, last_hash)) = sequencer.last() else {
+        sequencer.record(number, hash);
+        return vec![header];
+    };
+
+    if sequencer.contains(number, hash) {
+        // Already processed this exact block, e.g. redelivered by a slower redundant source.
+        // Must be checked before the gap/reorg branches below, since `number <= last_number`
+        // would otherwise be misread as a reorg.
+        return vec![];
+    }
+
+    if number > last_number && parent_hash == last_hash {
+        sequencer.record(number, hash);
+        return vec![header];
+    }
+
+    if number > last_number {
+        // The tip moved forward but doesn't link up to our tip. This is always handled as a gap
+        // (dropped notifications): the canonical blocks after `last_number` are fetched and
+        // replayed. A fork that replaced our tip itself is not counted as a reorg here, and
+        // blocks at or below `last_number` are not replayed.
+        record_block_gap();
+        warn!(from = last_number + 1, to = number, "Detected block gap, backfilling");
+        let mut headers = backfill(provider, last_number + 1, number - 1).await;
+        headers.push(header);
+        for header in &headers {
+            if let Some((n, h)) = header_key(header) {
+                sequencer.record(n, h);
+            }
+        }
+        return headers;
+    }
+
+    // Only reached for `number <= last_number` (a higher block that doesn't link up is handled
+    // as a gap above): a reorg. Walk back through recent history for the common ancestor and
+    // replay the new branch from there; if the ancestor isn't in recent history, only the new
+    // header itself is replayed.
+    let ancestor_number = sequencer
+        .recent
+        .iter()
+        .rev()
+        .find(|(_, h)| *h == parent_hash)
+        .map(|(n, _)| *n);
+    let reorg_depth = last_number.saturating_sub(ancestor_number.unwrap_or(number.saturating_sub(1)));
+    record_reorg(reorg_depth);
+    warn!(reorg_depth, new_tip = number, "Detected reorg, replaying new branch");
+
+    let from = ancestor_number.map(|n| n + 1).unwrap_or(number);
+    let mut headers = backfill(provider, from, number.saturating_sub(1)).await;
+    headers.push(header);
+    for header in &headers {
+        if let Some((n, h)) = header_key(header) {
+            sequencer.record(n, h);
+        }
+    }
+    headers
+}
+
+/// Capacity of the broadcast channel used to publish processed heads to other subsystems. A
+/// subscriber that falls behind by more than this many updates misses the oldest ones rather
+/// than stalling the cleanup loop; use [`next_head_update`] to have that counted as a metric.
+const HEAD_UPDATES_CHANNEL_CAPACITY: usize = 16;
+
+/// A processed canonical head, published on the broadcast channel returned by
+/// `spawn_clean_orderpool_job` for other subsystems to subscribe to.
+#[derive(Debug, Clone)]
+pub struct HeadUpdate {
+    pub block_number: u64,
+    pub block_hash: H256,
+    pub timestamp: U256,
+}
+
+/// Receives the next `HeadUpdate` from a `head_tx.subscribe()` subscription, recording lag as a
+/// metric if this subscriber fell behind instead of silently skipping the missed updates.
+pub async fn next_head_update(rx: &mut broadcast::Receiver<HeadUpdate>) -> Option<HeadUpdate> {
+    loop {
+        match rx.recv().await {
+            Ok(update) => return Some(update),
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                record_head_update_lag(skipped);
+            }
+            Err(broadcast::error::RecvError::Closed) => return None,
+        }
+    }
+}
 
 /// Performs maintenance operations on every new header by calling OrderPool::head_updated.
 /// Also calls some functions to generate metrics.
+///
+/// Returns the job's `JoinHandle` alongside a [`broadcast::Sender`] that publishes every
+/// processed head; other subsystems can call `.subscribe()` on it to receive head notifications
+/// without duplicating node connections.
 pub async fn spawn_clean_orderpool_job<SProvider: StateProviderFactory + Clone + 'static>(
     config: OrderInputConfig,
     provider_factory: SProvider,
     orderpool: Arc<Mutex<OrderPool>>,
     global_cancellation: CancellationToken,
-) -> eyre::Result<JoinHandle<()>> {
-    let ipc = Ipc::connect(config.ipc_path).await?;
-    let provider = Provider::new(ipc);
-    {
-        // quickly check that we can subscribe, before moving provider into the task
-        let sub = provider.subscribe_blocks().await?;
-        sub.unsubscribe().await.unwrap_or_default();
+) -> eyre::Result<(JoinHandle<()>, broadcast::Sender<HeadUpdate>)> {
+    let mut ipc_paths = vec![config.ipc_path];
+    ipc_paths.extend(config.extra_ipc_paths);
+
+    // Each stream is tagged with the provider that's delivering it, so that gap/reorg backfill
+    // (see `sequence_header`) queries whichever source actually produced the winning header
+    // instead of always hitting one hard-coded "primary" node.
+    let mut sources = Vec::with_capacity(ipc_paths.len());
+    for ipc_path in ipc_paths {
+        let (provider, stream) = connect_block_source(ipc_path).await?;
+        let tagged: Pin<Box<dyn Stream<Item = (Provider<Ipc>, BlockHeader)> + Send>> =
+            Box::pin(stream.map(move |header| (provider.clone(), header)));
+        sources.push(tagged);
     }
 
+    let (head_tx, _head_rx) = broadcast::channel(HEAD_UPDATES_CHANNEL_CAPACITY);
+
+    let head_tx_job = head_tx.clone();
     let handle = tokio::spawn(async move {
         info!("Clean orderpool job: started");
 
-        let new_block_stream = match provider.subscribe_blocks().await {
-            Ok(stream) => stream.take_until(global_cancellation.cancelled()),
-            Err(err) => {
-                error!("Failed to subscribe to a new block stream: {:?}", err);
-                global_cancellation.cancel();
-                return;
-            }
-        };
+        let new_block_stream = merge_fastest_wins(sources).take_until(global_cancellation.cancelled());
         let mut new_block_stream = pin!(new_block_stream);
+        let mut sequencer = BlockSequencer::new();
 
-        while let Some(block) = new_block_stream.next().await {
-            let block_number = block.number.unwrap_or_default().as_u64();
-            set_current_block(block_number);
-            let state = match provider_factory.latest() {
-                Ok(state) => state,
-                Err(err) => {
-                    error!("Failed to get latest state: {}", err);
-                    // @Metric error count
-                    continue;
-                }
-            };
-
-            let mut orderpool = orderpool.lock().unwrap();
-            let start = Instant::now();
-
-            orderpool.head_updated(block_number, &state);
-
-            let update_time = start.elapsed();
-            let (tx_count, bundle_count) = orderpool.content_count();
-            set_ordepool_count(tx_count, bundle_count);
-            debug!(
-                block_number,
-                tx_count,
-                bundle_count,
-                update_time_ms = update_time.as_millis(),
-                "Cleaned orderpool",
-            );
+        while let Some((provider, block)) = new_block_stream.next().await {
+            let headers = sequence_header(&mut sequencer, &provider, block).await;
+
+            for header in headers {
+                let block_number = header.number.unwrap_or_default().as_u64();
+                set_current_block(block_number);
+                let state = match provider_factory.latest() {
+                    Ok(state) => state,
+                    Err(err) => {
+                        error!("Failed to get latest state: {}", err);
+                        // @Metric error count
+                        continue;
+                    }
+                };
+
+                let mut orderpool = orderpool.lock().unwrap();
+                let start = Instant::now();
+
+                orderpool.head_updated(block_number, &state);
+
+                let update_time = start.elapsed();
+                let (tx_count, bundle_count) = orderpool.content_count();
+                set_ordepool_count(tx_count, bundle_count);
+                debug!(
+                    block_number,
+                    tx_count,
+                    bundle_count,
+                    update_time_ms = update_time.as_millis(),
+                    "Cleaned orderpool",
+                );
+                drop(orderpool);
+
+                // No active subscribers is a perfectly normal state; ignore the error.
+                let _ = head_tx_job.send(HeadUpdate {
+                    block_number,
+                    block_hash: header.hash.unwrap_or_default(),
+                    timestamp: header.timestamp,
+                });
+            }
         }
 
         global_cancellation.cancel();
         info!("Clean orderpool job: finished");
     });
-    Ok(handle)
+    Ok((handle, head_tx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::providers::MockProvider;
+
+    fn header(number: u64, hash: u64, parent: u64) -> BlockHeader {
+        BlockHeader {
+            number: Some(U64::from(number)),
+            hash: Some(H256::from_low_u64_be(hash)),
+            parent_hash: H256::from_low_u64_be(parent),
+            ..Default::default()
+        }
+    }
+
+    fn mock_provider() -> Provider<MockProvider> {
+        Provider::new(MockProvider::new())
+    }
+
+    #[tokio::test]
+    async fn contiguous_blocks_are_forwarded_one_by_one() {
+        let provider = mock_provider();
+        let mut sequencer = BlockSequencer::new();
+
+        let out = sequence_header(&mut sequencer, &provider, header(100, 100, 99)).await;
+        assert_eq!(out, vec![header(100, 100, 99)]);
+
+        let out = sequence_header(&mut sequencer, &provider, header(101, 101, 100)).await;
+        assert_eq!(out, vec![header(101, 101, 100)]);
+    }
+
+    #[tokio::test]
+    async fn gap_triggers_backfill_of_missing_blocks() {
+        let provider = mock_provider();
+        let mut sequencer = BlockSequencer::new();
+
+        sequence_header(&mut sequencer, &provider, header(100, 100, 99)).await;
+
+        // Block 101's subscription notification was dropped; 102 arrives directly, so the
+        // sequencer must backfill 101 before forwarding 102.
+        provider.as_ref().push(header(101, 101, 100)).unwrap();
+        let out = sequence_header(&mut sequencer, &provider, header(102, 102, 101)).await;
+        assert_eq!(out, vec![header(101, 101, 100), header(102, 102, 101)]);
+    }
+
+    #[tokio::test]
+    async fn reorg_replays_the_new_branch() {
+        let provider = mock_provider();
+        let mut sequencer = BlockSequencer::new();
+
+        sequence_header(&mut sequencer, &provider, header(100, 100, 99)).await;
+        sequence_header(&mut sequencer, &provider, header(101, 101, 100)).await;
+
+        // A competing block 101' replaces 101 on top of the same parent 100.
+        let out = sequence_header(&mut sequencer, &provider, header(101, 201, 100)).await;
+        assert_eq!(out, vec![header(101, 201, 100)]);
+
+        // The sequencer's tip is now the fork; the next block must link up against it.
+        let out = sequence_header(&mut sequencer, &provider, header(102, 102, 201)).await;
+        assert_eq!(out, vec![header(102, 102, 201)]);
+    }
+
+    #[tokio::test]
+    async fn duplicate_from_a_slower_source_is_dropped() {
+        let provider = mock_provider();
+        let mut sequencer = BlockSequencer::new();
+
+        for n in [100, 101, 102] {
+            sequence_header(&mut sequencer, &provider, header(n, n, n - 1)).await;
+        }
+
+        // A slower source redelivers block 101, already recorded — not a reorg.
+        let out = sequence_header(&mut sequencer, &provider, header(101, 101, 100)).await;
+        assert!(out.is_empty());
+
+        // The tip must still be 102, so the next real block links up normally instead of
+        // looking like a gap or reorg against a stale tip of 101.
+        let out = sequence_header(&mut sequencer, &provider, header(103, 103, 102)).await;
+        assert_eq!(out, vec![header(103, 103, 102)]);
+    }
+
+    #[tokio::test]
+    async fn merge_fastest_wins_prefers_whichever_source_arrives_first() {
+        let fast: Pin<Box<dyn Stream<Item = BlockHeader> + Send>> =
+            Box::pin(futures::stream::iter([header(100, 100, 99), header(101, 101, 100)]));
+        let stalled: Pin<Box<dyn Stream<Item = BlockHeader> + Send>> =
+            Box::pin(futures::stream::pending());
+
+        let mut merged = pin!(merge_fastest_wins(vec![fast, stalled]));
+
+        let first = tokio::time::timeout(Duration::from_secs(1), merged.next())
+            .await
+            .expect("a stalled source must not block a healthy one")
+            .unwrap();
+        assert_eq!(first, header(100, 100, 99));
+
+        let second = tokio::time::timeout(Duration::from_secs(1), merged.next())
+            .await
+            .expect("a stalled source must not block a healthy one")
+            .unwrap();
+        assert_eq!(second, header(101, 101, 100));
+    }
+
+    #[tokio::test]
+    async fn poll_blocks_emits_each_new_block_number_once() {
+        let provider = mock_provider();
+        // The mock hands back the most recently pushed response first, so responses are pushed
+        // in the reverse of the order in which `poll_blocks` requests them.
+        provider.as_ref().push(header(102, 102, 101)).unwrap();
+        provider.as_ref().push(U64::from(102u64)).unwrap();
+        // The tip is unchanged on this tick, so no block should be fetched or emitted for it.
+        provider.as_ref().push(U64::from(101u64)).unwrap();
+        provider.as_ref().push(header(101, 101, 100)).unwrap();
+        provider.as_ref().push(U64::from(101u64)).unwrap();
+
+        let mut stream = pin!(poll_blocks(provider, Duration::from_millis(1)));
+        let first = stream.next().await.unwrap();
+        assert_eq!(first, header(101, 101, 100));
+        let second = stream.next().await.unwrap();
+        assert_eq!(second, header(102, 102, 101));
+    }
 }
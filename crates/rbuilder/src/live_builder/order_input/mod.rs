@@ -0,0 +1,14 @@
+use std::path::PathBuf;
+
+pub mod clean_orderpool;
+
+/// Configuration for the live order-input subsystem that feeds [`clean_orderpool`].
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct OrderInputConfig {
+    /// Primary node's IPC endpoint used to watch for new heads.
+    pub ipc_path: PathBuf,
+    /// Additional IPC endpoints multiplexed alongside the primary one for lower head latency.
+    /// IPC only for now; WS source support is left for a follow-up.
+    #[serde(default)]
+    pub extra_ipc_paths: Vec<PathBuf>,
+}
@@ -0,0 +1,38 @@
+use once_cell::sync::Lazy;
+use prometheus::{register_int_counter, IntCounter};
+
+static BLOCK_GAPS_DETECTED: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "block_gaps_detected_total",
+        "Number of gaps between consecutive block headers detected and backfilled"
+    )
+    .unwrap()
+});
+
+static REORG_DEPTH_TOTAL: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "reorg_depth_total",
+        "Cumulative depth in blocks of reorgs detected while processing new heads"
+    )
+    .unwrap()
+});
+
+pub fn record_block_gap() {
+    BLOCK_GAPS_DETECTED.inc();
+}
+
+pub fn record_reorg(depth: u64) {
+    REORG_DEPTH_TOTAL.inc_by(depth);
+}
+
+static HEAD_UPDATE_LAG_TOTAL: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "head_update_lag_total",
+        "Number of head updates a broadcast subscriber missed after falling behind"
+    )
+    .unwrap()
+});
+
+pub fn record_head_update_lag(skipped: u64) {
+    HEAD_UPDATE_LAG_TOTAL.inc_by(skipped);
+}